@@ -0,0 +1,72 @@
+//! Alert payloads emitted by the eBPF probes over `PerfEventArray` maps and
+//! decoded by the userspace daemon for reporting.
+
+use crate::{
+    key::File,
+    mount::{FsType, MountPath},
+};
+
+/// Emitted when the `sb_mount` hook denies a mount attempt.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct SbMount {
+    pub pid: u32,
+    pub binprm_dev: u32,
+    pub binprm_inode: u64,
+    /// Filesystem type passed to `mount(2)`, e.g. `b"tmpfs"`.
+    pub fstype: FsType,
+    /// Raw `MS_*` mount flags passed to `mount(2)`.
+    pub flags: u64,
+    /// Mount source (`dev_name`), e.g. `b"/dev/loop0"`.
+    pub dev_name: MountPath,
+    /// Resolved mount target path, e.g. `b"/mnt"`.
+    pub target: MountPath,
+}
+
+impl SbMount {
+    pub fn new(
+        pid: u32,
+        binprm_file: File,
+        fstype: FsType,
+        flags: u64,
+        dev_name: MountPath,
+        target: MountPath,
+    ) -> Self {
+        Self {
+            pid,
+            binprm_dev: binprm_file.dev as u32,
+            binprm_inode: binprm_file.inode,
+            fstype,
+            flags,
+            dev_name,
+            target,
+        }
+    }
+}
+
+/// Emitted when the `capable` hook denies a capability check.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Capable {
+    pub pid: u32,
+    pub binprm_dev: u32,
+    pub binprm_inode: u64,
+    pub cap: i32,
+}
+
+impl Capable {
+    pub fn new(pid: u32, binprm_file: File, cap: i32) -> Self {
+        Self {
+            pid,
+            binprm_dev: binprm_file.dev as u32,
+            binprm_inode: binprm_file.inode,
+            cap,
+        }
+    }
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for SbMount {}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for Capable {}
@@ -0,0 +1,31 @@
+//! Types for the `capable` hook, which gates on the Linux *capability* being
+//! requested (e.g. `CAP_SYS_ADMIN`) rather than a specific operation.
+
+use crate::key::File;
+
+/// Reserved capability number meaning "any capability", mirroring
+/// [`crate::key::FILE_WILDCARD`] for the binary dimension.
+pub const CAP_WILDCARD: i32 = -1;
+
+/// Policy map key for the `capable` hook: a binary identity paired with the
+/// specific capability it is requesting.
+///
+/// `cap` is widened to `i64` so the struct has no trailing padding: `File`
+/// is 16 bytes at 8-byte alignment, and a trailing `i32` would otherwise
+/// leave 4 uninitialized padding bytes in a struct compared byte-for-byte as
+/// a BPF map key (see the comment on `key::File`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct CapableKey {
+    pub binprm_file: File,
+    pub cap: i64,
+}
+
+impl CapableKey {
+    pub const fn new(binprm_file: File, cap: i32) -> Self {
+        Self {
+            binprm_file,
+            cap: cap as i64,
+        }
+    }
+}
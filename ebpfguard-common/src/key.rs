@@ -0,0 +1,33 @@
+//! Compound keys used by LSM policy maps.
+//!
+//! Inode numbers are only unique within a single filesystem, so keying a
+//! policy map on a bare inode lets the same number on two different block
+//! devices alias one binary (or mount target) for another. Every such map
+//! keys on the device together with the inode instead.
+
+/// Identifies a file uniquely across filesystems.
+///
+/// `dev` is widened to `u64` (rather than the `dev_t`-sized `u32`) purely so
+/// the struct has no padding: both fields are then 8-byte aligned and the
+/// whole struct is compared byte-for-byte as a BPF map key, where an
+/// uninitialized padding byte would make two logically-identical keys built
+/// at different call sites fail to compare equal.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct File {
+    pub dev: u64,
+    pub inode: u64,
+}
+
+impl File {
+    pub const fn new(dev: u32, inode: u64) -> Self {
+        Self {
+            dev: dev as u64,
+            inode,
+        }
+    }
+}
+
+/// Reserved `File` value meaning "any file", used as a wildcard key in
+/// policy maps, superseding the old bare-inode `INODE_WILDCARD`.
+pub const FILE_WILDCARD: File = File::new(u32::MAX, u64::MAX);
@@ -0,0 +1,11 @@
+#![cfg_attr(not(feature = "user"), no_std)]
+
+//! Types shared between the `ebpfguard-ebpf` probes and the userspace
+//! `ebpfguard` daemon: alert payloads sent up through perf event arrays, and
+//! the handful of constants both sides need to agree on.
+
+pub mod alerts;
+pub mod capable;
+pub mod key;
+pub mod mount;
+pub mod policy;
@@ -0,0 +1,36 @@
+//! Types describing the extra mount-specific security surface (filesystem
+//! type, mount flags, and mount source and target paths) that the `sb_mount`
+//! hook matches policy against, beyond the calling binary's inode.
+
+/// A filesystem type name (e.g. `b"tmpfs"`), truncated and NUL-padded to a
+/// fixed length so it can be used as a `HashMap` key from eBPF.
+pub type FsType = [u8; 16];
+
+/// Reserved `FsType` value meaning "any filesystem type", mirroring
+/// [`crate::key::FILE_WILDCARD`] for the binary dimension.
+pub const FSTYPE_WILDCARD: FsType = [0xff; 16];
+
+// Mount flag bits from `include/uapi/linux/mount.h`, duplicated here so
+// policy authors can compose `MountFlags` without pulling in kernel headers.
+pub const MS_RDONLY: u64 = 1;
+pub const MS_NOSUID: u64 = 2;
+pub const MS_NODEV: u64 = 4;
+pub const MS_NOEXEC: u64 = 8;
+pub const MS_REMOUNT: u64 = 32;
+pub const MS_BIND: u64 = 4096;
+
+/// Per-binary constraint on the mount flags it is allowed to pass: bits set
+/// in `required_set` must all be present in the flags argument, and bits set
+/// in `required_clear` must all be absent.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct MountFlags {
+    pub required_set: u64,
+    pub required_clear: u64,
+}
+
+/// A truncated, NUL-padded mount source (`dev_name`, e.g. `b"/dev/loop0"`) or
+/// resolved absolute target path, used as an `LpmTrie` prefix key from eBPF
+/// so policy can match "under /dev/loop*" or "under /proc" rather than only
+/// an exact string.
+pub type MountPath = [u8; 64];
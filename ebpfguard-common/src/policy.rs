@@ -0,0 +1,31 @@
+//! Per-inode policy bits stored in kernel inode-local storage
+//! (`BPF_MAP_TYPE_INODE_STORAGE`), one allow/deny bit pair per hook. This is
+//! the O(1), collision-free fast path consulted before falling back to the
+//! global wildcard maps; entries that need a key finer than "this binary"
+//! (e.g. `capable`'s per-capability allow/deny) still live in those maps.
+//!
+//! Each cached entry is stamped with the policy generation (see
+//! `POLICY_GENERATION` in `ebpfguard-ebpf::maps`) it was resolved under,
+//! using [`pack`]/[`unpack`]. Userspace bumps that generation after writing
+//! to any `ALLOWED_*`/`DENIED_*` map, so a cached entry whose stamp doesn't
+//! match the current generation is stale — the policy changed since it was
+//! cached — and must be treated as a miss rather than trusted forever.
+
+pub const SB_MOUNT_ALLOW: u8 = 1 << 0;
+pub const SB_MOUNT_DENY: u8 = 1 << 1;
+pub const CAPABLE_ALLOW_ANY: u8 = 1 << 2;
+pub const CAPABLE_DENY_ANY: u8 = 1 << 3;
+
+/// Packs a cached decision bitfield together with the policy generation it
+/// was computed under, for storage as a single `INODE_POLICY` value.
+#[inline(always)]
+pub const fn pack(bits: u8, generation: u32) -> u64 {
+    (generation as u64) << 8 | bits as u64
+}
+
+/// Unpacks a value produced by [`pack`] back into its bits and the
+/// generation they were cached under.
+#[inline(always)]
+pub const fn unpack(raw: u64) -> (u8, u32) {
+    ((raw & 0xff) as u8, (raw >> 8) as u32)
+}
@@ -0,0 +1,43 @@
+use aya_bpf::{cty::c_long, helpers::bpf_probe_read_kernel};
+use ebpfguard_common::key::File;
+
+/// Resolves the `struct inode *` of the currently executing binary
+/// (`current->mm->exe_file->f_inode`), or null if it can't be resolved.
+///
+/// Hooks use this both to identify the binary via [`current_binprm_file`]
+/// and, directly, as the key into `BPF_MAP_TYPE_INODE_STORAGE` maps.
+pub fn current_binprm_inode_ptr() -> Result<*mut vmlinux::inode, c_long> {
+    let task = unsafe { aya_bpf::helpers::bpf_get_current_task() } as *const vmlinux::task_struct;
+
+    let mm = unsafe { bpf_probe_read_kernel(&(*task).mm)? };
+    if mm.is_null() {
+        return Ok(core::ptr::null_mut());
+    }
+
+    let exe_file = unsafe { bpf_probe_read_kernel(&(*mm).exe_file)? };
+    if exe_file.is_null() {
+        return Ok(core::ptr::null_mut());
+    }
+
+    unsafe { bpf_probe_read_kernel(&(*exe_file).f_inode) }
+}
+
+/// Resolves the `(dev, inode)` pair identifying the currently executing
+/// binary, unique across filesystems.
+pub fn current_binprm_file() -> Result<File, c_long> {
+    let inode = current_binprm_inode_ptr()?;
+    if inode.is_null() {
+        return Ok(File::new(0, 0));
+    }
+
+    let i_ino = unsafe { bpf_probe_read_kernel(&(*inode).i_ino)? };
+
+    let sb = unsafe { bpf_probe_read_kernel(&(*inode).i_sb)? };
+    let dev = if sb.is_null() {
+        0
+    } else {
+        unsafe { bpf_probe_read_kernel(&(*sb).s_dev)? }
+    };
+
+    Ok(File::new(dev, i_ino))
+}
@@ -0,0 +1,176 @@
+use aya_bpf::{cty::c_long, maps::HashMap, programs::LsmContext, BpfContext};
+use ebpfguard_common::{
+    alerts,
+    capable::{CapableKey, CAP_WILDCARD},
+    key::{File, FILE_WILDCARD},
+    policy,
+};
+
+use crate::{
+    binprm::{current_binprm_file, current_binprm_inode_ptr},
+    maps::{
+        ALERT_CAPABLE, ALLOWED_CAPABLE, DENIED_CAPABLE, INODE_POLICY, INODE_STORAGE_GET_CREATE,
+        POLICY_GENERATION,
+    },
+    Action, Mode,
+};
+
+/// Inspects the context of the `capable`/`cap_capable` LSM hook and decides
+/// whether to allow or deny the requested capability based on the state of
+/// the `ALLOWED_CAPABLE` and `DENIED_CAPABLE` maps.
+///
+/// Unlike the per-operation hooks, this gates on the Linux capability itself
+/// (e.g. `CAP_SYS_ADMIN`), closing the gap between an operation hook and the
+/// privilege that authorizes it.
+///
+/// If denied, the operation is logged to the `ALERT_CAPABLE` map.
+pub fn capable(ctx: LsmContext) -> Result<Action, c_long> {
+    let binprm_file = current_binprm_file()?;
+    let binprm_inode_ptr = current_binprm_inode_ptr()?;
+    let cap = unsafe { ctx.arg::<i32>(2) };
+
+    let wildcard = CapableKey::new(FILE_WILDCARD, CAP_WILDCARD);
+
+    if unsafe { ALLOWED_CAPABLE.get(&wildcard).is_some() } {
+        return Ok(check_conditions_and_alert(
+            &ctx,
+            &DENIED_CAPABLE,
+            binprm_file,
+            binprm_inode_ptr,
+            cap,
+            Mode::Denylist,
+        ));
+    }
+
+    if unsafe { DENIED_CAPABLE.get(&wildcard).is_some() } {
+        return Ok(check_conditions_and_alert(
+            &ctx,
+            &ALLOWED_CAPABLE,
+            binprm_file,
+            binprm_inode_ptr,
+            cap,
+            Mode::Allowlist,
+        ));
+    }
+
+    Ok(Action::Allow)
+}
+
+#[inline(always)]
+fn check_conditions_and_alert(
+    ctx: &LsmContext,
+    map: &HashMap<CapableKey, u8>,
+    binprm_file: File,
+    binprm_inode_ptr: *mut vmlinux::inode,
+    cap: i32,
+    mode: Mode,
+) -> Action {
+    match check_conditions(map, binprm_file, binprm_inode_ptr, cap, mode) {
+        Action::Deny => {
+            ALERT_CAPABLE.output(ctx, &alerts::Capable::new(ctx.pid(), binprm_file, cap), 0);
+            Action::Deny
+        }
+        action => action,
+    }
+}
+
+#[inline(always)]
+fn check_conditions(
+    map: &HashMap<CapableKey, u8>,
+    binprm_file: File,
+    binprm_inode_ptr: *mut vmlinux::inode,
+    cap: i32,
+    mode: Mode,
+) -> Action {
+    let generation = current_policy_generation();
+
+    // O(1) fast path for a blanket "any capability" decision on this binary,
+    // attached directly to its inode instead of scanned out of a HashMap.
+    // Only trusted if it was cached under the policy generation still in
+    // effect — otherwise `ALLOWED_CAPABLE`/`DENIED_CAPABLE` have been
+    // written to since, and this entry is stale.
+    if !binprm_inode_ptr.is_null() {
+        if let Some(raw) =
+            unsafe { INODE_POLICY.get_ptr_mut(binprm_inode_ptr, INODE_STORAGE_GET_CREATE) }
+        {
+            let (bits, cached_generation) = policy::unpack(unsafe { *raw });
+            if cached_generation == generation {
+                if bits & policy::CAPABLE_DENY_ANY != 0 {
+                    return Action::Deny;
+                }
+                if bits & policy::CAPABLE_ALLOW_ANY != 0 {
+                    // The cached bit already *is* the verdict ("this binary
+                    // may request any capability"), not a hint about which
+                    // map this call happens to be scanning — unlike the
+                    // `mode`-keyed lookups below, it must not be flipped to
+                    // `Deny` just because the caller's current pass is over
+                    // `DENIED_CAPABLE`.
+                    return Action::Allow;
+                }
+            }
+        }
+    }
+
+    // Finer-grained, per-capability entries still live in the HashMap.
+    let keys = [
+        CapableKey::new(binprm_file, cap),
+        CapableKey::new(binprm_file, CAP_WILDCARD),
+        CapableKey::new(FILE_WILDCARD, cap),
+        CapableKey::new(FILE_WILDCARD, CAP_WILDCARD),
+    ];
+
+    for key in keys {
+        if unsafe { map.get(&key).is_some() } {
+            let verdict = match mode {
+                Mode::Allowlist => Action::Allow,
+                Mode::Denylist => Action::Deny,
+            };
+            // Only the "any capability" keys mean what `CAPABLE_ALLOW_ANY`/
+            // `CAPABLE_DENY_ANY` mean; a hit on a specific-capability key
+            // doesn't license caching a blanket bit for this binary.
+            if key.cap == CAP_WILDCARD as i64 {
+                cache_capable_decision(binprm_inode_ptr, verdict, generation);
+            }
+            return verdict;
+        }
+    }
+
+    match mode {
+        Mode::Allowlist => Action::Deny,
+        Mode::Denylist => Action::Allow,
+    }
+}
+
+/// Reads the policy generation userspace last bumped after writing to
+/// `ALLOWED_CAPABLE`/`DENIED_CAPABLE`, defaulting to `0` before any write
+/// has happened.
+#[inline(always)]
+fn current_policy_generation() -> u32 {
+    unsafe { POLICY_GENERATION.get(0).copied().unwrap_or(0) }
+}
+
+/// Populates the `capable` fast-path bits in `INODE_POLICY` for `inode_ptr`
+/// once a blanket "any capability" verdict has been resolved from the
+/// `ALLOWED_CAPABLE`/`DENIED_CAPABLE` maps, stamped with `generation` so a
+/// later policy write invalidates it. The next call for this binary, if the
+/// generation hasn't moved on, hits the O(1) inode-storage path instead of
+/// scanning the map again.
+#[inline(always)]
+fn cache_capable_decision(inode_ptr: *mut vmlinux::inode, verdict: Action, generation: u32) {
+    if inode_ptr.is_null() {
+        return;
+    }
+
+    if let Some(raw) =
+        unsafe { INODE_POLICY.get_ptr_mut(inode_ptr, INODE_STORAGE_GET_CREATE) }
+    {
+        let bit = match verdict {
+            Action::Allow => policy::CAPABLE_ALLOW_ANY,
+            Action::Deny => policy::CAPABLE_DENY_ANY,
+        };
+        // Keep other hooks' bits cached under the same generation.
+        let (bits, cached_generation) = policy::unpack(unsafe { *raw });
+        let bits = if cached_generation == generation { bits | bit } else { bit };
+        unsafe { *raw = policy::pack(bits, generation) };
+    }
+}
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+mod binprm;
+mod capable;
+mod maps;
+mod sb_mount;
+
+use aya_bpf::{macros::lsm, programs::LsmContext};
+
+/// Outcome of a policy decision, returned by every hook handler and converted
+/// to the `i32` the LSM hook itself must return (`0` to allow, negative to
+/// deny).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Allow,
+    Deny,
+}
+
+impl From<Action> for i32 {
+    fn from(action: Action) -> i32 {
+        match action {
+            Action::Allow => 0,
+            Action::Deny => -1,
+        }
+    }
+}
+
+/// Whether a policy map for a given hook is being consulted as an allowlist
+/// (deny unless present) or a denylist (allow unless present).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Allowlist,
+    Denylist,
+}
+
+#[lsm(name = "sb_mount")]
+pub fn sb_mount(ctx: LsmContext) -> i32 {
+    match sb_mount::sb_mount(ctx) {
+        Ok(action) => action.into(),
+        Err(_) => Action::Deny.into(),
+    }
+}
+
+#[lsm(name = "capable")]
+pub fn capable(ctx: LsmContext) -> i32 {
+    match capable::capable(ctx) {
+        Ok(action) => action.into(),
+        Err(_) => Action::Deny.into(),
+    }
+}
+
+#[cfg(not(test))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    unsafe { core::hint::unreachable_unchecked() }
+}
@@ -0,0 +1,77 @@
+use aya_bpf::{
+    macros::map,
+    maps::{lpm_trie::LpmTrie, Array, HashMap, InodeStorage, PerfEventArray},
+};
+use ebpfguard_common::{
+    alerts,
+    capable::CapableKey,
+    key::File,
+    mount::{FsType, MountFlags, MountPath},
+};
+
+#[map]
+pub static ALLOWED_SB_MOUNT: HashMap<File, u8> = HashMap::with_max_entries(1024, 0);
+
+#[map]
+pub static DENIED_SB_MOUNT: HashMap<File, u8> = HashMap::with_max_entries(1024, 0);
+
+#[map]
+pub static ALLOWED_SB_MOUNT_FSTYPE: HashMap<FsType, u8> = HashMap::with_max_entries(1024, 0);
+
+#[map]
+pub static DENIED_SB_MOUNT_FSTYPE: HashMap<FsType, u8> = HashMap::with_max_entries(1024, 0);
+
+/// Per-binary required/forbidden mount flag bits, keyed by `binprm_file`.
+#[map]
+pub static SB_MOUNT_REQUIRED_FLAGS: HashMap<File, MountFlags> = HashMap::with_max_entries(1024, 0);
+
+// Source/target paths are matched by prefix ("under /dev/loop*", "under
+// /proc"), which an exact-keyed `HashMap` can't express; an `LpmTrie` stores
+// each policy entry as a prefix and does the longest-prefix-match lookup in
+// the kernel.
+#[map]
+pub static ALLOWED_SB_MOUNT_SOURCE: LpmTrie<MountPath, u8> = LpmTrie::with_max_entries(1024, 0);
+
+#[map]
+pub static DENIED_SB_MOUNT_SOURCE: LpmTrie<MountPath, u8> = LpmTrie::with_max_entries(1024, 0);
+
+#[map]
+pub static ALLOWED_SB_MOUNT_TARGET: LpmTrie<MountPath, u8> = LpmTrie::with_max_entries(1024, 0);
+
+#[map]
+pub static DENIED_SB_MOUNT_TARGET: LpmTrie<MountPath, u8> = LpmTrie::with_max_entries(1024, 0);
+
+#[map]
+pub static ALERT_SB_MOUNT: PerfEventArray<alerts::SbMount> = PerfEventArray::new(0);
+
+#[map]
+pub static ALLOWED_CAPABLE: HashMap<CapableKey, u8> = HashMap::with_max_entries(1024, 0);
+
+#[map]
+pub static DENIED_CAPABLE: HashMap<CapableKey, u8> = HashMap::with_max_entries(1024, 0);
+
+#[map]
+pub static ALERT_CAPABLE: PerfEventArray<alerts::Capable> = PerfEventArray::new(0);
+
+/// Flag for `bpf_inode_storage_get` requesting the entry be created if
+/// absent, mirroring `BPF_LOCAL_STORAGE_GET_F_CREATE` from the kernel UAPI.
+pub const INODE_STORAGE_GET_CREATE: u64 = 1;
+
+/// Bumped by userspace after any write to the `ALLOWED_*`/`DENIED_*` maps
+/// above. Single-entry, so reading "the current generation" is one cheap
+/// array lookup; compared against the generation each `INODE_POLICY` entry
+/// was cached under (`policy::pack`/`policy::unpack`) so a policy update is
+/// never shadowed by a stale cached verdict for a binary seen before the
+/// update.
+#[map]
+pub static POLICY_GENERATION: Array<u32> = Array::with_max_entries(1, 0);
+
+/// Per-inode policy bits (see `ebpfguard_common::policy`), packed together
+/// with the policy generation they were cached under: the O(1),
+/// collision-free fast path consulted before the global wildcard maps above,
+/// but only when its generation still matches `POLICY_GENERATION`. Attached
+/// directly to the kernel inode object and garbage-collected with it, so
+/// there are no stale entries once a binary is deleted — and the generation
+/// stamp means there are no stale entries across a policy update either.
+#[map]
+pub static INODE_POLICY: InodeStorage<u64> = InodeStorage::new(0);
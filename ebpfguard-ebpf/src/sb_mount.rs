@@ -1,15 +1,43 @@
-use aya_bpf::{maps::HashMap, programs::LsmContext, BpfContext, cty::c_long};
-use ebpfguard_common::{alerts, consts::INODE_WILDCARD};
+use aya_bpf::{
+    cty::{c_char, c_long},
+    helpers::{bpf_probe_read_kernel, bpf_probe_read_kernel_str_bytes},
+    maps::{
+        lpm_trie::{Key, LpmTrie},
+        HashMap,
+    },
+    programs::LsmContext,
+    BpfContext,
+};
+use ebpfguard_common::{
+    alerts,
+    key::{File, FILE_WILDCARD},
+    mount::{FsType, MountPath, FSTYPE_WILDCARD},
+    policy,
+};
 
 use crate::{
-    binprm::current_binprm_inode,
-    maps::{ALERT_SB_MOUNT, ALLOWED_SB_MOUNT, DENIED_SB_MOUNT},
+    binprm::{current_binprm_file, current_binprm_inode_ptr},
+    maps::{
+        ALERT_SB_MOUNT, ALLOWED_SB_MOUNT, ALLOWED_SB_MOUNT_FSTYPE, ALLOWED_SB_MOUNT_SOURCE,
+        ALLOWED_SB_MOUNT_TARGET, DENIED_SB_MOUNT, DENIED_SB_MOUNT_FSTYPE, DENIED_SB_MOUNT_SOURCE,
+        DENIED_SB_MOUNT_TARGET, INODE_POLICY, INODE_STORAGE_GET_CREATE, POLICY_GENERATION,
+    },
     Action, Mode,
 };
 
+/// Everything about a single `sb_mount` attempt that policy can match on,
+/// beyond the calling binary itself.
+struct MountAttempt {
+    fstype: FsType,
+    flags: u64,
+    dev_name: MountPath,
+    target: MountPath,
+}
+
 /// Inspects the context of `sb_mount` LSM hook and decides whether to allow or
 /// deny the operation based on the state of the `ALLOWED_SB_MOUNT` and
-/// `DENIED_SB_MOUNT` maps.
+/// `DENIED_SB_MOUNT` maps, additionally narrowed by the filesystem type,
+/// mount flags, source device and target path being requested.
 ///
 /// If denied, the operation is logged to the `ALERT_SB_MOUNT` map.
 ///
@@ -24,29 +52,200 @@ use crate::{
 /// }
 /// ```
 pub fn sb_mount(ctx: LsmContext) -> Result<Action, c_long> {
-    let binprm_inode = current_binprm_inode()?;
+    let binprm_file = current_binprm_file()?;
+    let binprm_inode_ptr = current_binprm_inode_ptr()?;
+    let (target, target_truncated) = read_target(&ctx)?;
+    let attempt = MountAttempt {
+        fstype: read_fstype(&ctx)?,
+        flags: unsafe { ctx.arg::<u64>(3) },
+        dev_name: read_dev_name(&ctx)?,
+        target,
+    };
 
-    if unsafe { ALLOWED_SB_MOUNT.get(&INODE_WILDCARD).is_some() } {
-        return Ok(check_conditions_and_alert(&ctx, &DENIED_SB_MOUNT, binprm_inode, Mode::Denylist));
+    if target_truncated {
+        // The resolved target is incomplete (too deep for MAX_TARGET_DEPTH,
+        // too long for the MountPath buffer, or with a component name too
+        // long for its read buffer):
+        // matching it against ALLOWED_SB_MOUNT_TARGET/DENIED_SB_MOUNT_TARGET
+        // would risk comparing the wrong prefix, so deny outright rather
+        // than silently mis-evaluate a path that only looks root-anchored.
+        ALERT_SB_MOUNT.output(
+            &ctx,
+            &alerts::SbMount::new(
+                ctx.pid(),
+                binprm_file,
+                attempt.fstype,
+                attempt.flags,
+                attempt.dev_name,
+                attempt.target,
+            ),
+            0,
+        );
+        return Ok(Action::Deny);
     }
 
-    if unsafe { DENIED_SB_MOUNT.get(&INODE_WILDCARD).is_some() } {
-        return Ok(check_conditions_and_alert(&ctx, &ALLOWED_SB_MOUNT, binprm_inode, Mode::Allowlist));
+    if unsafe { ALLOWED_SB_MOUNT.get(&FILE_WILDCARD).is_some() } {
+        return Ok(check_conditions_and_alert(
+            &ctx,
+            &DENIED_SB_MOUNT,
+            binprm_file,
+            binprm_inode_ptr,
+            &attempt,
+            Mode::Denylist,
+        ));
+    }
+
+    if unsafe { DENIED_SB_MOUNT.get(&FILE_WILDCARD).is_some() } {
+        return Ok(check_conditions_and_alert(
+            &ctx,
+            &ALLOWED_SB_MOUNT,
+            binprm_file,
+            binprm_inode_ptr,
+            &attempt,
+            Mode::Allowlist,
+        ));
     }
 
     Ok(Action::Allow)
 }
 
+/// Reads the `type` argument (`const char *`) of `sb_mount` into a fixed,
+/// truncated buffer so it can be used as a `HashMap` key.
+#[inline(always)]
+fn read_fstype(ctx: &LsmContext) -> Result<FsType, c_long> {
+    let mut fstype = [0u8; 16];
+    let type_ptr = unsafe { ctx.arg::<*const c_char>(2) };
+    if !type_ptr.is_null() {
+        unsafe { bpf_probe_read_kernel_str_bytes(type_ptr as *const u8, &mut fstype)? };
+    }
+    Ok(fstype)
+}
+
+/// Reads the `dev_name` argument (`const char *`) of `sb_mount` — the mount
+/// source, e.g. `/dev/sda1` — into a fixed, truncated buffer.
+#[inline(always)]
+fn read_dev_name(ctx: &LsmContext) -> Result<MountPath, c_long> {
+    let mut dev_name = [0u8; 64];
+    let dev_name_ptr = unsafe { ctx.arg::<*const c_char>(0) };
+    if !dev_name_ptr.is_null() {
+        unsafe { bpf_probe_read_kernel_str_bytes(dev_name_ptr as *const u8, &mut dev_name)? };
+    }
+    Ok(dev_name)
+}
+
+/// Upper bound on the number of path components walked by [`read_target`];
+/// keeps the `d_parent` walk a fixed-trip-count loop, as the verifier
+/// requires. Generous enough for realistic mount targets; paths deeper than
+/// this (or too long for the `MountPath` buffer, or with a component name of
+/// 15 bytes or more) are reported as truncated rather than silently returning
+/// a partial path.
+const MAX_TARGET_DEPTH: usize = 32;
+
+/// Resolves the `path` argument (`const struct path *`) of `sb_mount` — the
+/// mount target — to a real absolute path (not just its leaf dentry name) by
+/// walking `d_parent` up to [`MAX_TARGET_DEPTH`] components, so policy can
+/// match "under /proc" rather than only the mountpoint's last component.
+///
+/// Returns `(path, truncated)`. When `truncated` is `true`, the real root
+/// wasn't reached — the depth bound or the `MountPath` buffer ran out first,
+/// or a component name filled its 16-byte read buffer and may have been cut
+/// short — so `path` is incomplete and must not be trusted for prefix
+/// matching; callers should deny rather than risk
+/// matching it against the wrong policy prefix.
+#[inline(always)]
+fn read_target(ctx: &LsmContext) -> Result<(MountPath, bool), c_long> {
+    let mut scratch = [0u8; 64];
+    let mut pos = scratch.len();
+
+    let path_ptr = unsafe { ctx.arg::<*const vmlinux::path>(1) };
+    if path_ptr.is_null() {
+        return Ok((scratch, false));
+    }
+
+    let mut dentry = unsafe { bpf_probe_read_kernel(&(*path_ptr).dentry)? };
+    let mut reached_root = false;
+    let mut truncated = false;
+
+    for _ in 0..MAX_TARGET_DEPTH {
+        if dentry.is_null() {
+            reached_root = true;
+            break;
+        }
+
+        let parent = unsafe { bpf_probe_read_kernel(&(*dentry).d_parent)? };
+        if parent.is_null() || parent == dentry {
+            // Reached the root dentry: nothing more to prepend.
+            reached_root = true;
+            break;
+        }
+
+        let mut component = [0u8; 16];
+        let name_ptr = unsafe { bpf_probe_read_kernel(&(*dentry).d_name.name)? };
+        if !name_ptr.is_null() {
+            unsafe { bpf_probe_read_kernel_str_bytes(name_ptr as *const u8, &mut component)? };
+        }
+        let len = component.iter().position(|&b| b == 0).unwrap_or(component.len());
+        if len + 1 >= component.len() {
+            // The name filled `component`, so it may have been cut short.
+            truncated = true;
+            break;
+        }
+
+        // Prepend "/component" to what's already in `scratch`, stopping once
+        // it no longer fits rather than overflowing the buffer.
+        if pos < len + 1 {
+            truncated = true;
+            break;
+        }
+        pos -= len;
+        scratch[pos..pos + len].copy_from_slice(&component[..len]);
+        pos -= 1;
+        scratch[pos] = b'/';
+
+        dentry = parent;
+    }
+
+    // The loop only exits without `reached_root` or `truncated` set by
+    // exhausting MAX_TARGET_DEPTH iterations while ancestors still remained.
+    if !reached_root && !truncated {
+        truncated = true;
+    }
+
+    if pos == scratch.len() {
+        // No components were resolved (e.g. mounting onto the root itself).
+        let mut target = [0u8; 64];
+        target[0] = b'/';
+        return Ok((target, truncated));
+    }
+
+    let mut target = [0u8; 64];
+    target[..scratch.len() - pos].copy_from_slice(&scratch[pos..]);
+    Ok((target, truncated))
+}
+
 #[inline(always)]
 fn check_conditions_and_alert(
     ctx: &LsmContext,
-    map: &HashMap<u64, u8>,
-    binprm_inode: u64,
+    map: &HashMap<File, u8>,
+    binprm_file: File,
+    binprm_inode_ptr: *mut vmlinux::inode,
+    attempt: &MountAttempt,
     mode: Mode,
 ) -> Action {
-    match check_conditions(map, binprm_inode, mode) {
+    match check_conditions(map, binprm_file, binprm_inode_ptr, attempt, mode) {
         Action::Deny => {
-            ALERT_SB_MOUNT.output(ctx, &alerts::SbMount::new(ctx.pid(), binprm_inode), 0);
+            ALERT_SB_MOUNT.output(
+                ctx,
+                &alerts::SbMount::new(
+                    ctx.pid(),
+                    binprm_file,
+                    attempt.fstype,
+                    attempt.flags,
+                    attempt.dev_name,
+                    attempt.target,
+                ),
+                0,
+            );
             Action::Deny
         }
         action => action,
@@ -54,19 +253,40 @@ fn check_conditions_and_alert(
 }
 
 #[inline(always)]
-fn check_conditions(map: &HashMap<u64, u8>, binprm_inode: u64, mode: Mode) -> Action {
-    if unsafe { map.get(&INODE_WILDCARD).is_some() } {
-        return match mode {
-            Mode::Allowlist => Action::Allow,
-            Mode::Denylist => Action::Deny,
-        };
+fn check_conditions(
+    map: &HashMap<File, u8>,
+    binprm_file: File,
+    binprm_inode_ptr: *mut vmlinux::inode,
+    attempt: &MountAttempt,
+    mode: Mode,
+) -> Action {
+    let generation = current_policy_generation();
+
+    // O(1) fast path: a per-binary decision attached directly to its inode,
+    // with no wildcard scan and no stale entries once the binary is deleted
+    // — or once a policy write bumps `POLICY_GENERATION` past the generation
+    // this entry was cached under.
+    if let Some(action) = check_inode_policy(binprm_file, binprm_inode_ptr, attempt, generation) {
+        return action;
     }
 
-    if unsafe { map.get(&binprm_inode).is_some() } {
-        return match mode {
-            Mode::Allowlist => Action::Allow,
-            Mode::Denylist => Action::Deny,
-        };
+    // Fall back to the global map: the "any inode" wildcard, and the
+    // exact-match entry for this specific binary (`INODE_POLICY` is only
+    // populated lazily, so an existing per-binary policy must stay reachable
+    // here rather than going dead).
+    let verdict = match mode {
+        Mode::Allowlist => Action::Allow,
+        Mode::Denylist => Action::Deny,
+    };
+
+    if unsafe { map.get(&FILE_WILDCARD).is_some() } {
+        cache_mount_decision(binprm_inode_ptr, verdict, generation);
+        return finish_checking_mount_attempt(binprm_file, attempt, verdict);
+    }
+
+    if unsafe { map.get(&binprm_file).is_some() } {
+        cache_mount_decision(binprm_inode_ptr, verdict, generation);
+        return finish_checking_mount_attempt(binprm_file, attempt, verdict);
     }
 
     match mode {
@@ -74,3 +294,167 @@ fn check_conditions(map: &HashMap<u64, u8>, binprm_inode: u64, mode: Mode) -> Ac
         Mode::Denylist => Action::Allow,
     }
 }
+
+/// Reads the policy generation userspace last bumped after writing to
+/// `ALLOWED_SB_MOUNT`/`DENIED_SB_MOUNT`, defaulting to `0` before any write
+/// has happened.
+#[inline(always)]
+fn current_policy_generation() -> u32 {
+    unsafe { POLICY_GENERATION.get(0).copied().unwrap_or(0) }
+}
+
+/// Populates the `sb_mount` fast-path bits in `INODE_POLICY` for
+/// `inode_ptr` once a verdict has been resolved from the `ALLOWED_SB_MOUNT`/
+/// `DENIED_SB_MOUNT` maps, stamped with `generation` so a later policy write
+/// invalidates it. The next `sb_mount` call for this binary, if the
+/// generation hasn't moved on, hits the O(1) inode-storage path instead of
+/// scanning the map again.
+#[inline(always)]
+fn cache_mount_decision(inode_ptr: *mut vmlinux::inode, verdict: Action, generation: u32) {
+    if inode_ptr.is_null() {
+        return;
+    }
+
+    if let Some(raw) =
+        unsafe { INODE_POLICY.get_ptr_mut(inode_ptr, INODE_STORAGE_GET_CREATE) }
+    {
+        let bit = match verdict {
+            Action::Allow => policy::SB_MOUNT_ALLOW,
+            Action::Deny => policy::SB_MOUNT_DENY,
+        };
+        // Keep other hooks' bits cached under the same generation.
+        let (bits, cached_generation) = policy::unpack(unsafe { *raw });
+        let bits = if cached_generation == generation { bits | bit } else { bit };
+        unsafe { *raw = policy::pack(bits, generation) };
+    }
+}
+
+#[inline(always)]
+fn check_inode_policy(
+    binprm_file: File,
+    binprm_inode_ptr: *mut vmlinux::inode,
+    attempt: &MountAttempt,
+    generation: u32,
+) -> Option<Action> {
+    if binprm_inode_ptr.is_null() {
+        return None;
+    }
+
+    let raw = unsafe { *INODE_POLICY.get_ptr_mut(binprm_inode_ptr, INODE_STORAGE_GET_CREATE)? };
+    let (bits, cached_generation) = policy::unpack(raw);
+    if cached_generation != generation {
+        // Stale: a policy write happened since this was cached.
+        return None;
+    }
+
+    if bits & policy::SB_MOUNT_DENY != 0 {
+        return Some(Action::Deny);
+    }
+    if bits & policy::SB_MOUNT_ALLOW != 0 {
+        // The cached bit already *is* the verdict ("this binary is on the
+        // allow list"), not a hint about which map this call happens to be
+        // scanning — unlike the `mode`-keyed lookups in `check_conditions`,
+        // it must not be flipped to `Deny` just because the caller's
+        // current pass is over `DENIED_SB_MOUNT`.
+        return Some(finish_checking_mount_attempt(binprm_file, attempt, Action::Allow));
+    }
+    None
+}
+
+/// Once the calling binary itself has matched with `verdict`, further narrow
+/// the decision by the filesystem type, mount flags, source device and
+/// target path being requested; any of them can still veto an
+/// otherwise-matching inode, but none of them can turn a `Deny` into an
+/// `Allow`.
+#[inline(always)]
+fn finish_checking_mount_attempt(
+    binprm_file: File,
+    attempt: &MountAttempt,
+    verdict: Action,
+) -> Action {
+    if !fstype_permitted(&attempt.fstype) {
+        return Action::Deny;
+    }
+
+    if !flags_permitted(binprm_file, attempt.flags) {
+        return Action::Deny;
+    }
+
+    if !path_permitted(&ALLOWED_SB_MOUNT_SOURCE, &DENIED_SB_MOUNT_SOURCE, &attempt.dev_name) {
+        return Action::Deny;
+    }
+
+    if !path_permitted(&ALLOWED_SB_MOUNT_TARGET, &DENIED_SB_MOUNT_TARGET, &attempt.target) {
+        return Action::Deny;
+    }
+
+    verdict
+}
+
+/// Checks `fstype` against `ALLOWED_SB_MOUNT_FSTYPE`/`DENIED_SB_MOUNT_FSTYPE`
+/// using the same Allowlist/Denylist convention as every other hook: a
+/// wildcard entry in one map must be present before the *other* map's
+/// specific entries have any effect at all, e.g. adding
+/// `ALLOWED_SB_MOUNT_FSTYPE["tmpfs"]` alone does nothing until
+/// `DENIED_SB_MOUNT_FSTYPE[FSTYPE_WILDCARD]` is also set — it does not, by
+/// itself, restrict mounts to `tmpfs`.
+///
+/// [`path_permitted`] below looks similar but is *not* wildcard-gated, and
+/// its `ALLOWED_SB_MOUNT_SOURCE`/`_TARGET` entries do not restrict anything:
+/// only a matching `DENIED_SB_MOUNT_SOURCE`/`_TARGET` entry refuses a path.
+/// Don't unify the two without revisiting both call sites in
+/// `finish_checking_mount_attempt`.
+#[inline(always)]
+fn fstype_permitted(fstype: &FsType) -> bool {
+    if unsafe { ALLOWED_SB_MOUNT_FSTYPE.get(&FSTYPE_WILDCARD).is_some() } {
+        return unsafe { DENIED_SB_MOUNT_FSTYPE.get(fstype).is_none() };
+    }
+
+    if unsafe { DENIED_SB_MOUNT_FSTYPE.get(&FSTYPE_WILDCARD).is_some() } {
+        return unsafe { ALLOWED_SB_MOUNT_FSTYPE.get(fstype).is_some() };
+    }
+
+    true
+}
+
+#[inline(always)]
+fn flags_permitted(binprm_file: File, flags: u64) -> bool {
+    match unsafe { crate::maps::SB_MOUNT_REQUIRED_FLAGS.get(&binprm_file) } {
+        Some(required) => {
+            flags & required.required_set == required.required_set
+                && flags & required.required_clear == 0
+        }
+        None => true,
+    }
+}
+
+/// `path`'s full length in bits, used as the lookup key's prefix length so
+/// the trie returns its longest *stored* prefix that matches — e.g. a
+/// `/dev/loop` entry matches a `/dev/loop0` lookup, and a `/proc` entry
+/// matches any path under it.
+const MOUNT_PATH_BITS: u32 = (core::mem::size_of::<MountPath>() * 8) as u32;
+
+/// Checks `path` against an `ALLOWED`/`DENIED` pair of source or target
+/// maps. Unlike [`fstype_permitted`], there is no wildcard gate. A path is
+/// refused exactly when some entry of `denied` is a prefix of it; an entry in
+/// `allowed` neither refuses paths outside it nor overrides a matching
+/// `denied` entry, whichever of the two prefixes is longer.
+#[inline(always)]
+fn path_permitted(
+    allowed: &LpmTrie<MountPath, u8>,
+    denied: &LpmTrie<MountPath, u8>,
+    path: &MountPath,
+) -> bool {
+    let key = Key::new(MOUNT_PATH_BITS, *path);
+
+    if unsafe { allowed.get(&key, 0).is_some() } {
+        // An allow entry matched; any matching deny entry still vetoes it.
+        return unsafe { denied.get(&key, 0).is_none() };
+    }
+
+    if unsafe { denied.get(&key, 0).is_some() } {
+        return false;
+    }
+
+    true
+}